@@ -10,7 +10,7 @@ fn bench_contains(c: &mut Criterion) {
     let mut r = rand::thread_rng();
     let mut set = Ipset::default();
     for _ in 0..num_networks {
-        set.insert(&random_network());
+        set.insert(&random_network(), ());
     }
 
     c.bench_function("Contains", |b| {
@@ -27,7 +27,7 @@ fn bench_insert(c: &mut Criterion) {
             let mut set = Ipset::default();
             b.iter(|| {
                 for _ in 0..*d {
-                    set.insert(&random_network())
+                    set.insert(&random_network(), ())
                 }
             });
         });
@@ -0,0 +1,119 @@
+//! Collapsing a list of networks into the minimal equivalent set: dropping
+//! any network already covered by a broader one, and merging sibling
+//! prefixes into their shared parent.
+
+use crate::difference::{contains, range};
+use ipnetwork::Ipv4Network;
+
+/// Return the minimal set of networks equivalent to the union of
+/// `networks`: networks already covered by a broader network are dropped,
+/// and sibling prefixes that together exactly cover their parent are merged
+/// into it, repeating until no more merges apply.
+pub fn aggregate(networks: &[Ipv4Network]) -> Vec<Ipv4Network> {
+    let mut sorted: Vec<Ipv4Network> = networks.to_vec();
+    sorted.sort_by_key(|net| (u32::from(net.network()), net.prefix()));
+    sorted.dedup();
+
+    let mut kept: Vec<Ipv4Network> = Vec::new();
+    for net in sorted {
+        if !kept.iter().any(|broader| contains(broader, &net)) {
+            kept.push(net);
+        }
+    }
+
+    loop {
+        let merged = merge_siblings(&kept);
+        if merged.len() == kept.len() {
+            return merged;
+        }
+        kept = merged;
+    }
+}
+
+/// One pass of merging adjacent sibling prefixes into their shared parent.
+fn merge_siblings(networks: &[Ipv4Network]) -> Vec<Ipv4Network> {
+    let mut sorted: Vec<Ipv4Network> = networks.to_vec();
+    sorted.sort_by_key(|net| (u32::from(net.network()), net.prefix()));
+
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < sorted.len() {
+        if let Some(&next) = sorted.get(i + 1) {
+            if are_siblings(&sorted[i], &next) {
+                out.push(parent(&sorted[i]));
+                i += 2;
+                continue;
+            }
+        }
+        out.push(sorted[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Are `a` and `b` the two halves of the same `(a.prefix() - 1)`-bit parent?
+fn are_siblings(a: &Ipv4Network, b: &Ipv4Network) -> bool {
+    if a.prefix() == 0 || a.prefix() != b.prefix() {
+        return false;
+    }
+    let (a_lo, _) = range(a);
+    let (b_lo, _) = range(b);
+    let size = 1u32 << (32 - a.prefix());
+    // Avoid overflowing u32 for /1 siblings, where size is already 1 << 31.
+    (a_lo / size).is_multiple_of(2) && b_lo == a_lo + size
+}
+
+/// The `(prefix - 1)`-bit network containing `net`.
+fn parent(net: &Ipv4Network) -> Ipv4Network {
+    Ipv4Network::new(net.network(), net.prefix() - 1).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drops_network_covered_by_broader_one() {
+        let networks = vec![
+            "10.0.0.0/8".parse().unwrap(),
+            "10.10.0.0/16".parse().unwrap(),
+        ];
+        assert_eq!(aggregate(&networks), vec!["10.0.0.0/8".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_merges_sibling_pair() {
+        let networks = vec![
+            "10.0.0.0/25".parse().unwrap(),
+            "10.0.0.128/25".parse().unwrap(),
+        ];
+        assert_eq!(aggregate(&networks), vec!["10.0.0.0/24".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_merges_transitively_up_multiple_levels() {
+        let networks = vec![
+            "10.0.0.0/26".parse().unwrap(),
+            "10.0.0.64/26".parse().unwrap(),
+            "10.0.0.128/25".parse().unwrap(),
+        ];
+        assert_eq!(aggregate(&networks), vec!["10.0.0.0/24".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_merges_half_address_space_siblings_without_overflow() {
+        let networks = vec!["0.0.0.0/1".parse().unwrap(), "128.0.0.0/1".parse().unwrap()];
+        assert_eq!(aggregate(&networks), vec!["0.0.0.0/0".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_unrelated_networks_are_unchanged() {
+        let networks = vec![
+            "10.0.0.0/24".parse().unwrap(),
+            "192.168.0.0/24".parse().unwrap(),
+        ];
+        let mut result = aggregate(&networks);
+        result.sort_by_key(|net| u32::from(net.network()));
+        assert_eq!(result, networks);
+    }
+}
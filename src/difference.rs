@@ -0,0 +1,124 @@
+//! Subtracting a set of networks from another, producing the minimal list of
+//! CIDR blocks that cover what's left.
+
+use ipnetwork::Ipv4Network;
+use std::net::Ipv4Addr;
+
+/// Compute `net` minus the union of `exclude`, as the minimal list of CIDR
+/// blocks covering whatever of `net` isn't covered by some network in
+/// `exclude`.
+///
+/// This is handy for turning "everything except these" into concrete blocks,
+/// e.g. for firewall rules.
+pub fn difference(net: Ipv4Network, exclude: &[Ipv4Network]) -> Vec<Ipv4Network> {
+    exclude.iter().fold(vec![net], |nets, excluded| {
+        nets.into_iter()
+            .flat_map(|n| subtract_one(n, excluded))
+            .collect()
+    })
+}
+
+/// Compute `a` minus `b`.
+fn subtract_one(a: Ipv4Network, b: &Ipv4Network) -> Vec<Ipv4Network> {
+    if !overlaps(&a, b) {
+        return vec![a];
+    }
+    if contains(b, &a) {
+        return vec![];
+    }
+
+    // `b` overlaps `a` but doesn't cover all of it, so split `a` into its two
+    // halves and recurse into whichever half `b` overlaps.
+    let (left, right) = split(&a);
+    [left, right]
+        .into_iter()
+        .flat_map(|half| {
+            if overlaps(&half, b) {
+                subtract_one(half, b)
+            } else {
+                vec![half]
+            }
+        })
+        .collect()
+}
+
+/// Split a network into its two child prefixes, one bit longer.
+fn split(net: &Ipv4Network) -> (Ipv4Network, Ipv4Network) {
+    let child_prefix = net.prefix() + 1;
+    let base = u32::from(net.network());
+    let half_size = 1u32 << (32 - child_prefix);
+    let left = Ipv4Network::new(Ipv4Addr::from(base), child_prefix).unwrap();
+    let right = Ipv4Network::new(Ipv4Addr::from(base + half_size), child_prefix).unwrap();
+    (left, right)
+}
+
+/// Does `outer` fully cover `inner`?
+pub(crate) fn contains(outer: &Ipv4Network, inner: &Ipv4Network) -> bool {
+    let (outer_lo, outer_hi) = range(outer);
+    let (inner_lo, inner_hi) = range(inner);
+    outer_lo <= inner_lo && inner_hi <= outer_hi
+}
+
+/// Do `a` and `b` share any addresses?
+fn overlaps(a: &Ipv4Network, b: &Ipv4Network) -> bool {
+    let (a_lo, a_hi) = range(a);
+    let (b_lo, b_hi) = range(b);
+    a_lo <= b_hi && b_lo <= a_hi
+}
+
+/// The inclusive `(first, last)` address range covered by `net`.
+pub(crate) fn range(net: &Ipv4Network) -> (u32, u32) {
+    let lo = u32::from(net.network());
+    let hi = if net.prefix() == 0 {
+        u32::MAX
+    } else {
+        lo + ((1u32 << (32 - net.prefix())) - 1)
+    };
+    (lo, hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_overlap_keeps_net_whole() {
+        let net = "10.0.0.0/24".parse().unwrap();
+        let exclude = vec!["192.168.0.0/24".parse().unwrap()];
+        assert_eq!(difference(net, &exclude), vec![net]);
+    }
+
+    #[test]
+    fn test_full_overlap_removes_everything() {
+        let net = "10.0.0.0/24".parse().unwrap();
+        let exclude = vec!["10.0.0.0/16".parse().unwrap()];
+        assert_eq!(difference(net, &exclude), Vec::<Ipv4Network>::new());
+    }
+
+    #[test]
+    fn test_partial_overlap_splits_into_remainder() {
+        let net = "10.0.0.0/24".parse().unwrap();
+        let exclude = vec!["10.0.0.128/25".parse().unwrap()];
+        assert_eq!(
+            difference(net, &exclude),
+            vec!["10.0.0.0/25".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_multiple_exclusions() {
+        let net = "10.0.0.0/24".parse().unwrap();
+        let exclude = vec![
+            "10.0.0.0/26".parse().unwrap(),
+            "10.0.0.192/26".parse().unwrap(),
+        ];
+        let remaining = difference(net, &exclude);
+        assert_eq!(
+            remaining,
+            vec![
+                "10.0.0.64/26".parse().unwrap(),
+                "10.0.0.128/26".parse().unwrap()
+            ]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! The IPv6 half of [`crate::IpSet`].
+//!
+//! [`Ipset6`] wraps [`crate::trie::GenericIpset`] instantiated for
+//! [`crate::trie::Ipv6Family`], so it's the exact same trie as the IPv4
+//! [`crate::Ipset`] and gets the same longest-prefix-match, containment and
+//! iteration behaviour for free.
+
+use crate::trie::{GenericIpset, Ipv6Family};
+use ipnetwork::Ipv6Network;
+use std::net::Ipv6Addr;
+
+/// Stores a set of IPv6 networks, mapping each to a value, and can find the
+/// most specific (longest-prefix) match for a given address.
+#[derive(Default)]
+pub struct Ipset6<T = ()>(GenericIpset<Ipv6Family, T>);
+
+impl<T> Ipset6<T> {
+    /// Associate `value` with `net`. Inserting the same network twice
+    /// overwrites the previous value.
+    pub fn insert(&mut self, net: &Ipv6Network, value: T) {
+        self.0.insert(net, value);
+    }
+
+    /// Find the most specific network in the set that contains `ip`, along
+    /// with the value associated with it.
+    pub fn longest_match(&self, ip: &Ipv6Addr) -> Option<(Ipv6Network, &T)> {
+        self.0.longest_match(ip)
+    }
+
+    /// Is the given IP in the set of IP networks?
+    pub fn contains(&self, ip: &Ipv6Addr) -> bool {
+        self.0.contains(ip)
+    }
+
+    /// Is the given network entirely covered by a network in the set? This
+    /// differs from `self.contains(&net.network())`, which only checks the
+    /// network's base address.
+    pub fn contains_network(&self, net: &Ipv6Network) -> bool {
+        self.0.contains_network(net)
+    }
+
+    /// Iterate over the networks stored in the set, along with their values.
+    pub fn iter(&self) -> std::vec::IntoIter<(Ipv6Network, &T)> {
+        self.0.iter()
+    }
+}
+
+impl Ipset6<()> {
+    /// Find the union of the given networks.
+    pub fn new(networks: &[Ipv6Network]) -> Self {
+        let mut this = Self::default();
+        for net in networks {
+            this.insert(net, ());
+        }
+        this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v6_basic() {
+        let networks = vec!["2001:db8::/32".parse().unwrap()];
+        let set = Ipset6::new(&networks);
+        assert!(set.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(!set.contains(&"2001:db9::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_v6_no_false_positive_from_unrelated_networks() {
+        // A per-bit summary that forgets which network contributed each bit
+        // would accept "2080::1" here, since its first 16 bits can each be
+        // satisfied by *some* combination of 2001:db8::/32 and fe80::/16.
+        // The trie only matches addresses that are an actual prefix of one
+        // inserted network.
+        let networks = vec![
+            "2001:db8::/32".parse().unwrap(),
+            "fe80::/16".parse().unwrap(),
+        ];
+        let set = Ipset6::new(&networks);
+        assert!(set.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(set.contains(&"fe80::1".parse().unwrap()));
+        assert!(!set.contains(&"2080::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_v6_zero_prefix_matches_everything() {
+        let networks = vec!["::/0".parse().unwrap()];
+        let set = Ipset6::new(&networks);
+        assert!(set.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(set.contains(&"::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_v6_longest_match_returns_most_specific_network() {
+        let mut set = Ipset6::default();
+        set.insert(&"2001:db8::/32".parse().unwrap(), "research");
+        set.insert(&"2001:db8:1::/48".parse().unwrap(), "lab");
+
+        let (net, value) = set
+            .longest_match(&"2001:db8:1::1".parse().unwrap())
+            .unwrap();
+        assert_eq!(net, "2001:db8:1::/48".parse().unwrap());
+        assert_eq!(*value, "lab");
+
+        let (net, value) = set.longest_match(&"2001:db8:2::1".parse().unwrap()).unwrap();
+        assert_eq!(net, "2001:db8::/32".parse().unwrap());
+        assert_eq!(*value, "research");
+
+        assert!(set.longest_match(&"2001:db9::1".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_v6_contains_network_requires_full_coverage() {
+        let networks = vec!["2001:db8::/32".parse().unwrap()];
+        let set = Ipset6::new(&networks);
+        assert!(set.contains_network(&"2001:db8::/48".parse().unwrap()));
+        assert!(set.contains_network(&"2001:db8::/32".parse().unwrap()));
+        assert!(!set.contains_network(&"2001:db8::/16".parse().unwrap()));
+        // Diverges from the stored prefix at bit 32, so none of it is covered.
+        assert!(!set.contains_network(&"2001:db9::/48".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_v6_iter_yields_stored_networks() {
+        let mut set = Ipset6::default();
+        set.insert(&"2001:db8::/32".parse().unwrap(), "research");
+        set.insert(&"fe80::/16".parse().unwrap(), "link-local");
+
+        let mut networks: Vec<_> = set.iter().map(|(net, value)| (net, *value)).collect();
+        networks.sort_by_key(|(net, _)| net.network());
+        assert_eq!(
+            networks,
+            vec![
+                ("2001:db8::/32".parse().unwrap(), "research"),
+                ("fe80::/16".parse().unwrap(), "link-local"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! A binary trie keyed on network address bits, giving an [`Ipset`] that
+//! maps each inserted network to a value and can answer *which* network
+//! matched a given address (longest-prefix match), not just whether one did.
+//!
+//! The trie itself doesn't know whether it's storing IPv4 or IPv6 networks;
+//! [`Family`] supplies the per-address-family conversions (bit width, how to
+//! walk an address bit by bit, how to rebuild a network from a matched
+//! prefix), and [`Ipset`]/[`crate::Ipset6`] are just [`GenericIpset`]
+//! instantiated for [`Ipv4Family`]/[`Ipv6Family`].
+
+use ipnetwork::{Ipv4Network, Ipv6Network};
+use std::marker::PhantomData;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// The address-family-specific operations a [`GenericIpset`] needs in order
+/// to store and look up networks without caring whether they're IPv4 or
+/// IPv6.
+pub trait Family {
+    /// The address type this family's networks are built from.
+    type Addr: Copy;
+    /// The CIDR network type this family stores.
+    type Network: Copy;
+
+    /// Number of bits in an address of this family (32 for IPv4, 128 for
+    /// IPv6).
+    const BITS: u8;
+
+    /// The bits of `addr`, most significant first.
+    fn addr_bits(addr: Self::Addr) -> Vec<bool>;
+
+    /// Rebuild an address from a prefix of its bits, padding whatever bits
+    /// weren't given with `0`.
+    fn addr_from_prefix_bits(bits: &[bool]) -> Self::Addr;
+
+    /// The base address of `net`.
+    fn network_addr(net: &Self::Network) -> Self::Addr;
+
+    /// The prefix length of `net`.
+    fn prefix(net: &Self::Network) -> u8;
+
+    /// The network of `prefix_len` bits starting at `addr`.
+    fn new_network(addr: Self::Addr, prefix_len: u8) -> Self::Network;
+}
+
+/// [`Family`] for IPv4 networks.
+pub struct Ipv4Family;
+
+impl Family for Ipv4Family {
+    type Addr = Ipv4Addr;
+    type Network = Ipv4Network;
+    const BITS: u8 = 32;
+
+    fn addr_bits(addr: Ipv4Addr) -> Vec<bool> {
+        bits_from_octets(&addr.octets())
+    }
+
+    fn addr_from_prefix_bits(bits: &[bool]) -> Ipv4Addr {
+        Ipv4Addr::from(octets_from_bits::<4>(bits))
+    }
+
+    fn network_addr(net: &Ipv4Network) -> Ipv4Addr {
+        net.network()
+    }
+
+    fn prefix(net: &Ipv4Network) -> u8 {
+        net.prefix()
+    }
+
+    fn new_network(addr: Ipv4Addr, prefix_len: u8) -> Ipv4Network {
+        let masked = Ipv4Network::new(addr, prefix_len)
+            .expect("prefix_len is at most 32")
+            .network();
+        Ipv4Network::new(masked, prefix_len).expect("prefix_len is at most 32")
+    }
+}
+
+/// [`Family`] for IPv6 networks.
+pub struct Ipv6Family;
+
+impl Family for Ipv6Family {
+    type Addr = Ipv6Addr;
+    type Network = Ipv6Network;
+    const BITS: u8 = 128;
+
+    fn addr_bits(addr: Ipv6Addr) -> Vec<bool> {
+        bits_from_octets(&addr.octets())
+    }
+
+    fn addr_from_prefix_bits(bits: &[bool]) -> Ipv6Addr {
+        Ipv6Addr::from(octets_from_bits::<16>(bits))
+    }
+
+    fn network_addr(net: &Ipv6Network) -> Ipv6Addr {
+        net.network()
+    }
+
+    fn prefix(net: &Ipv6Network) -> u8 {
+        net.prefix()
+    }
+
+    fn new_network(addr: Ipv6Addr, prefix_len: u8) -> Ipv6Network {
+        Ipv6Network::new(addr, prefix_len).expect("prefix_len is at most 128")
+    }
+}
+
+/// The bits of `octets`, most significant first.
+fn bits_from_octets(octets: &[u8]) -> Vec<bool> {
+    let mut out = Vec::with_capacity(octets.len() * 8);
+    for octet in octets {
+        for b in 0..8 {
+            out.push((octet >> (7 - b)) & 1 == 1);
+        }
+    }
+    out
+}
+
+/// Rebuild `N` octets from a prefix of their bits, padding whatever bits
+/// weren't given with `0`.
+fn octets_from_bits<const N: usize>(bits: &[bool]) -> [u8; N] {
+    let mut out = [0u8; N];
+    for (i, &bit) in bits.iter().enumerate() {
+        if bit {
+            out[i / 8] |= 1 << (7 - (i % 8));
+        }
+    }
+    out
+}
+
+/// One node in the trie. `children[0]`/`children[1]` are the subtrees
+/// reached by the next prefix bit being `0`/`1`; `value` is set once a
+/// network has been inserted that ends at this node.
+struct Node<T> {
+    children: [Option<Box<Node<T>>>; 2],
+    value: Option<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            children: [None, None],
+            value: None,
+        }
+    }
+}
+
+/// Maps each network in the set to a value, and can find the most specific
+/// (longest-prefix) match for a given address.
+///
+/// Unlike a representation that summarizes "does any inserted network have a
+/// 0/1 at this bit position" independently per bit, this trie keeps each
+/// inserted prefix as its own path, so a lookup can report which network
+/// matched rather than just whether some combination of bits did.
+///
+/// Generic over the address [`Family`] so IPv4 ([`Ipset`]) and IPv6
+/// ([`crate::Ipset6`]) share this same implementation.
+pub struct GenericIpset<F, T> {
+    root: Node<T>,
+    _family: PhantomData<F>,
+}
+
+impl<F, T> Default for GenericIpset<F, T> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+            _family: PhantomData,
+        }
+    }
+}
+
+impl<F: Family, T> GenericIpset<F, T> {
+    /// Associate `value` with `net`. Inserting the same network twice
+    /// overwrites the previous value.
+    pub fn insert(&mut self, net: &F::Network, value: T) {
+        let prefix_len = F::prefix(net) as usize;
+        let bits = F::addr_bits(F::network_addr(net));
+        let mut node = &mut self.root;
+        for &bit in &bits[..prefix_len] {
+            node = node.children[bit as usize].get_or_insert_with(Default::default);
+        }
+        node.value = Some(value);
+    }
+
+    /// Find the most specific network in the set that contains `addr`, along
+    /// with the value associated with it.
+    pub fn longest_match(&self, addr: &F::Addr) -> Option<(F::Network, &T)> {
+        let bits = F::addr_bits(*addr);
+        let mut node = &self.root;
+        let mut best: Option<(usize, &T)> = node.value.as_ref().map(|value| (0, value));
+        for (depth, &bit) in bits.iter().enumerate() {
+            node = match &node.children[bit as usize] {
+                Some(child) => child,
+                None => break,
+            };
+            if let Some(value) = &node.value {
+                best = Some((depth + 1, value));
+            }
+        }
+        best.map(|(prefix_len, value)| {
+            let prefix_addr = F::addr_from_prefix_bits(&bits[..prefix_len]);
+            (F::new_network(prefix_addr, prefix_len as u8), value)
+        })
+    }
+
+    /// Is the given address covered by any network in the set?
+    pub fn contains(&self, addr: &F::Addr) -> bool {
+        self.longest_match(addr).is_some()
+    }
+
+    /// Is the given network entirely covered by a network in the set? This
+    /// differs from `self.contains(&net.network())`, which only checks the
+    /// network's base address.
+    pub fn contains_network(&self, net: &F::Network) -> bool {
+        let prefix_len = F::prefix(net) as usize;
+        let bits = F::addr_bits(F::network_addr(net));
+        let mut node = &self.root;
+        if node.value.is_some() {
+            return true;
+        }
+        for &bit in &bits[..prefix_len] {
+            node = match &node.children[bit as usize] {
+                Some(child) => child,
+                None => return false,
+            };
+            if node.value.is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Iterate over the networks stored in the set, along with their values.
+    pub fn iter(&self) -> std::vec::IntoIter<(F::Network, &T)> {
+        self.into_iter()
+    }
+}
+
+impl<'a, F: Family, T> IntoIterator for &'a GenericIpset<F, T> {
+    type Item = (F::Network, &'a T);
+    type IntoIter = std::vec::IntoIter<(F::Network, &'a T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut out = Vec::new();
+        collect::<F, T>(&self.root, &[], &mut out);
+        out.into_iter()
+    }
+}
+
+/// Depth-first walk collecting each terminal node's network and value.
+/// `bits` is the path of bits taken to reach `node`.
+fn collect<'a, F: Family, T>(
+    node: &'a Node<T>,
+    bits: &[bool],
+    out: &mut Vec<(F::Network, &'a T)>,
+) {
+    if let Some(value) = &node.value {
+        let addr = F::addr_from_prefix_bits(bits);
+        out.push((F::new_network(addr, bits.len() as u8), value));
+    }
+    for (bit, child) in node.children.iter().enumerate() {
+        if let Some(child) = child {
+            let mut child_bits = bits.to_vec();
+            child_bits.push(bit == 1);
+            collect::<F, T>(child, &child_bits, out);
+        }
+    }
+}
+
+impl<F: Family> GenericIpset<F, ()> {
+    /// Find the union of the given networks.
+    pub fn new(networks: &[F::Network]) -> Self {
+        let mut this = Self::default();
+        for net in networks {
+            this.insert(net, ());
+        }
+        this
+    }
+}
+
+/// An IPv4 set mapping each inserted network to a value.
+pub type Ipset<T> = GenericIpset<Ipv4Family, T>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_32_cidr() {
+        let networks = vec!["10.10.0.32/32".parse().unwrap()];
+        let set = Ipset::new(&networks);
+        assert!(set.contains(&"10.10.0.32".parse().unwrap()));
+        assert!(!set.contains(&"203.10.0.32".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_partial_cidr() {
+        let networks = vec!["10.10.0.32/16".parse().unwrap()];
+        let set = Ipset::new(&networks);
+        assert!(set.contains(&"10.10.0.0".parse().unwrap()));
+        assert!(!set.contains(&"11.0.0.0".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_multiple() {
+        let networks = vec![
+            "10.10.0.0/16".parse().unwrap(),
+            "11.10.0.0/16".parse().unwrap(),
+        ];
+        let set = Ipset::new(&networks);
+        assert!(!set.contains(&"9.10.0.0".parse().unwrap()));
+        assert!(set.contains(&"10.10.0.0".parse().unwrap()));
+        assert!(set.contains(&"11.10.0.0".parse().unwrap()));
+        assert!(!set.contains(&"12.10.0.0".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_no_false_positive_from_unrelated_networks() {
+        // A per-bit summary that forgets which network contributed each bit
+        // would accept "138.0.0.0" here, since its first-octet bits can each
+        // be satisfied by *some* combination of 10.0.0.0/8 and
+        // 192.168.0.0/16. The trie only matches addresses that are an actual
+        // prefix of one inserted network.
+        let networks = vec![
+            "10.0.0.0/8".parse().unwrap(),
+            "192.168.0.0/16".parse().unwrap(),
+        ];
+        let set = Ipset::new(&networks);
+        assert!(set.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(set.contains(&"192.168.1.2".parse().unwrap()));
+        assert!(!set.contains(&"138.0.0.0".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_zero_prefix_matches_everything() {
+        let networks = vec!["0.0.0.0/0".parse().unwrap()];
+        let set = Ipset::new(&networks);
+        assert!(set.contains(&"1.2.3.4".parse().unwrap()));
+        assert!(set.contains(&"255.255.255.255".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_longest_match_returns_most_specific_network() {
+        let mut set = Ipset::default();
+        set.insert(&"10.0.0.0/8".parse().unwrap(), "private");
+        set.insert(&"10.10.0.0/16".parse().unwrap(), "office");
+
+        let (net, value) = set.longest_match(&"10.10.0.1".parse().unwrap()).unwrap();
+        assert_eq!(net, "10.10.0.0/16".parse().unwrap());
+        assert_eq!(*value, "office");
+
+        let (net, value) = set.longest_match(&"10.20.0.1".parse().unwrap()).unwrap();
+        assert_eq!(net, "10.0.0.0/8".parse().unwrap());
+        assert_eq!(*value, "private");
+
+        assert!(set.longest_match(&"11.0.0.0".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_contains_network_requires_full_coverage() {
+        let networks = vec!["10.0.0.0/16".parse().unwrap()];
+        let set = Ipset::new(&networks);
+        assert!(set.contains_network(&"10.0.0.0/24".parse().unwrap()));
+        assert!(set.contains_network(&"10.0.0.0/16".parse().unwrap()));
+        assert!(!set.contains_network(&"10.0.0.0/8".parse().unwrap()));
+        // Outside the stored network entirely, base address included.
+        assert!(!set.contains_network(&"10.1.0.0/24".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_iter_yields_stored_networks() {
+        let mut set = Ipset::default();
+        set.insert(&"10.0.0.0/8".parse().unwrap(), "private");
+        set.insert(&"192.168.0.0/16".parse().unwrap(), "office");
+
+        let mut networks: Vec<_> = set.iter().map(|(net, value)| (net, *value)).collect();
+        networks.sort_by_key(|(net, _)| net.network());
+        assert_eq!(
+            networks,
+            vec![
+                ("10.0.0.0/8".parse().unwrap(), "private"),
+                ("192.168.0.0/16".parse().unwrap(), "office"),
+            ]
+        );
+    }
+}